@@ -10,7 +10,7 @@ use crate::{format, Error};
 use super::{Context, Id, Profile};
 use ffi::*;
 use media;
-use ChannelLayout;
+use {ChannelLayout, Rational};
 
 pub struct Parameters {
     ptr: *mut AVCodecParameters,
@@ -77,6 +77,39 @@ impl Parameters {
         unsafe { (*self.as_ptr()).level as i32 }
     }
 
+    pub fn set_id(&mut self, value: Id) {
+        unsafe {
+            (*self.as_mut_ptr()).codec_id = value.into();
+        }
+    }
+
+    pub fn set_bit_rate(&mut self, value: usize) {
+        unsafe {
+            (*self.as_mut_ptr()).bit_rate = value as i64;
+        }
+    }
+
+    pub fn set_extradata(&mut self, data: &[u8]) {
+        unsafe {
+            let ptr = self.as_mut_ptr();
+
+            if !(*ptr).extradata.is_null() {
+                av_freep(&mut (*ptr).extradata as *mut _ as *mut _);
+            }
+
+            let extradata = av_mallocz(data.len() + AV_INPUT_BUFFER_PADDING_SIZE as usize) as *mut u8;
+
+            if extradata.is_null() {
+                panic!("out of memory");
+            }
+
+            (*ptr).extradata = extradata;
+            (*ptr).extradata_size = data.len() as i32;
+
+            slice::from_raw_parts_mut((*ptr).extradata, data.len()).copy_from_slice(data);
+        }
+    }
+
     pub fn video(mut self) -> Result<Video, Error> {
         match self.medium() {
             media::Type::Unknown => {
@@ -152,6 +185,17 @@ impl<C: AsRef<Context>> From<C> for Parameters {
     }
 }
 
+impl Parameters {
+    pub fn apply_to(&self, context: &mut Context) -> Result<(), Error> {
+        unsafe {
+            match avcodec_parameters_to_context(context.as_mut_ptr(), self.as_ptr()) {
+                0 => Ok(()),
+                e => Err(Error::from(e)),
+            }
+        }
+    }
+}
+
 pub struct Video(pub Parameters);
 
 impl Video {
@@ -170,6 +214,56 @@ impl Video {
     pub fn height(&self) -> u32 {
         unsafe { (*self.0.as_ptr()).height as u32 }
     }
+
+    pub fn set_format(&mut self, value: format::Pixel) {
+        unsafe {
+            (*self.0.as_mut_ptr()).format = mem::transmute::<AVPixelFormat, i32>(value.into());
+        }
+    }
+
+    pub fn set_width(&mut self, value: u32) {
+        unsafe {
+            (*self.0.as_mut_ptr()).width = value as i32;
+        }
+    }
+
+    pub fn set_height(&mut self, value: u32) {
+        unsafe {
+            (*self.0.as_mut_ptr()).height = value as i32;
+        }
+    }
+
+    pub fn set_aspect_ratio<R: Into<Rational>>(&mut self, value: R) {
+        unsafe {
+            (*self.0.as_mut_ptr()).sample_aspect_ratio = value.into().into();
+        }
+    }
+
+    /// Builds the `args` string expected by `filter::Graph::add` (or
+    /// `add_with_opts`) for the `buffer` source filter.
+    pub fn buffersrc_args(&self, time_base: Rational) -> String {
+        unsafe {
+            let sar = (*self.0.as_ptr()).sample_aspect_ratio;
+
+            // An unset (0/0) SAR is rejected by the `buffer` source filter,
+            // so fall back to the unknown-but-valid 1/1 ratio.
+            let (sar_num, sar_den) = if sar.num == 0 || sar.den == 0 {
+                (1, 1)
+            } else {
+                (sar.num, sar.den)
+            };
+
+            format!(
+                "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}/{}",
+                self.width(),
+                self.height(),
+                self.format(),
+                time_base,
+                sar_num,
+                sar_den,
+            )
+        }
+    }
 }
 
 impl Deref for Video {
@@ -210,6 +304,53 @@ impl Audio {
     pub fn channels(&self) -> u16 {
         unsafe { (*self.0.as_ptr()).channels as u16 }
     }
+
+    pub fn set_format(&mut self, value: format::Sample) {
+        unsafe {
+            (*self.0.as_mut_ptr()).format = mem::transmute::<AVSampleFormat, i32>(value.into());
+        }
+    }
+
+    pub fn set_rate(&mut self, value: u32) {
+        unsafe {
+            (*self.0.as_mut_ptr()).sample_rate = value as i32;
+        }
+    }
+
+    pub fn set_channels(&mut self, value: u16) {
+        unsafe {
+            (*self.0.as_mut_ptr()).channels = value as i32;
+        }
+    }
+
+    pub fn set_channel_layout(&mut self, value: ChannelLayout) {
+        unsafe {
+            (*self.0.as_mut_ptr()).channel_layout = value.bits();
+        }
+    }
+
+    /// Builds the `args` string expected by `filter::Graph::add` (or
+    /// `add_with_opts`) for the `abuffer` source filter.
+    pub fn abuffersrc_args(&self, time_base: Rational) -> String {
+        unsafe {
+            let mut layout = self.channel_layout().bits();
+
+            // An unset (0) channel_layout is rejected by the `abuffer`
+            // source filter, so derive the default layout for the known
+            // channel count instead of emitting a zero mask.
+            if layout == 0 {
+                layout = av_get_default_channel_layout(self.channels() as i32) as u64;
+            }
+
+            format!(
+                "sample_rate={}:sample_fmt={}:time_base={}:channel_layout=0x{:x}",
+                self.rate(),
+                self.format(),
+                time_base,
+                layout,
+            )
+        }
+    }
 }
 
 impl Deref for Audio {
@@ -3,10 +3,19 @@ use std::ffi::{CString, CStr};
 use std::str::from_utf8_unchecked;
 
 use ffi::*;
-use libc::c_int;
+use libc::{c_int, c_double};
 use ::Error;
+use ::Dictionary;
+use hwdevice;
+use hwframe;
 use super::{Context, Filter};
 
+bitflags! {
+	pub struct ThreadType: c_int {
+		const SLICE = AVFILTER_THREAD_SLICE as c_int;
+	}
+}
+
 pub struct Graph {
 	ptr: *mut AVFilterGraph,
 }
@@ -47,6 +56,59 @@ impl Graph {
 		}
 	}
 
+	/// Assigns an `av_buffer_ref` of `device` as the `hw_device_ctx` of every
+	/// filter context currently in the graph. Call this after adding the
+	/// filters that need it and before `validate`.
+	pub fn set_hw_device(&mut self, device: &hwdevice::Context) {
+		unsafe {
+			let graph      = self.as_mut_ptr();
+			let nb_filters = (*graph).nb_filters as isize;
+
+			for i in 0..nb_filters {
+				let filter_ctx = *(*graph).filters.offset(i);
+				let hw_ref     = av_buffer_ref(device.as_ptr() as *mut _);
+
+				if hw_ref.is_null() {
+					panic!("out of memory");
+				}
+
+				if !(*filter_ctx).hw_device_ctx.is_null() {
+					av_buffer_unref(&mut (*filter_ctx).hw_device_ctx);
+				}
+
+				(*filter_ctx).hw_device_ctx = hw_ref;
+			}
+		}
+	}
+
+	/// Sets the `hw_frames_ctx` of a buffersrc filter `context` via
+	/// `av_buffersrc_parameters_set`. This must be called before `validate`
+	/// (i.e. before `avfilter_graph_config` runs), or format negotiation for
+	/// the hardware frames will fail.
+	pub fn set_buffersrc_hw_frames_ctx(&mut self, context: &mut Context, frames: &hwframe::Context) -> Result<(), Error> {
+		unsafe {
+			let params = av_buffersrc_parameters_alloc();
+
+			if params.is_null() {
+				panic!("out of memory");
+			}
+
+			// av_buffersrc_parameters_set takes its own reference, so we
+			// hand it the borrowed pointer as-is rather than ref'ing it
+			// ourselves (which would leak, since nothing unrefs it after).
+			(*params).hw_frames_ctx = frames.as_ptr() as *mut _;
+
+			let result = av_buffersrc_parameters_set(context.as_mut_ptr(), params);
+
+			av_freep(&mut params as *mut _ as *mut _);
+
+			match result {
+				0 => Ok(()),
+				e => Err(Error::from(e)),
+			}
+		}
+	}
+
 	pub fn add<'a, 'b>(&'a mut self, filter: &Filter, name: &str, args: &str) -> Result<Context<'b>, Error> where 'a: 'b {
 		unsafe {
 			let mut context = ptr::null_mut();
@@ -64,6 +126,68 @@ impl Graph {
 		}
 	}
 
+	pub fn add_with_opts<'a, 'b>(&'a mut self, filter: &Filter, name: &str, args: &str, mut opts: Dictionary) -> Result<Context<'b>, Error> where 'a: 'b {
+		unsafe {
+			let context = avfilter_graph_alloc_filter(self.as_mut_ptr(),
+				filter.as_ptr(),
+				CString::new(name).unwrap().as_ptr());
+
+			if context.is_null() {
+				return Err(Error::InvalidData);
+			}
+
+			if !args.is_empty() {
+				match av_opt_set_from_string(context as *mut _,
+					CString::new(args).unwrap().as_ptr(),
+					ptr::null(),
+					CString::new("=").unwrap().as_ptr(),
+					CString::new(":").unwrap().as_ptr())
+				{
+					n if n >= 0 => (),
+					e           => return Err(Error::from(e)),
+				}
+			}
+
+			// avfilter_init_dict frees the dict it's handed and writes back
+			// any options it didn't recognize, so we disown `opts` into a
+			// raw pointer it can take and reclaim ownership of whatever
+			// comes back rather than dropping the (now stale) `Dictionary`.
+			let mut raw = opts.disown();
+			let result  = avfilter_init_dict(context, &mut raw);
+
+			opts = Dictionary::own(raw);
+
+			match result {
+				n if n >= 0 => Ok(Context::wrap(context)),
+				e           => Err(Error::from(e)),
+			}
+		}
+	}
+
+	pub fn set_threads(&mut self, threads: usize) {
+		unsafe {
+			(*self.as_mut_ptr()).nb_threads = threads as c_int;
+		}
+	}
+
+	pub fn set_thread_type(&mut self, thread_type: ThreadType) {
+		unsafe {
+			(*self.as_mut_ptr()).thread_type = thread_type.bits();
+		}
+	}
+
+	pub fn set_scale_sws_opts(&mut self, opts: &str) {
+		unsafe {
+			let ptr = self.as_mut_ptr();
+
+			if !(*ptr).scale_sws_opts.is_null() {
+				av_freep(&mut (*ptr).scale_sws_opts as *mut _ as *mut _);
+			}
+
+			(*ptr).scale_sws_opts = av_strdup(CString::new(opts).unwrap().as_ptr());
+		}
+	}
+
 	pub fn get<'a, 'b>(&'b mut self, name: &str) -> Option<Context<'b>> where 'a: 'b {
 		unsafe {
 			let ptr = avfilter_graph_get_filter(self.as_mut_ptr(), CString::new(name).unwrap().as_ptr());
@@ -89,6 +213,43 @@ impl Graph {
 		}
 	}
 
+	/// Sends a command to the filter(s) matching `target` (or `"all"` to
+	/// broadcast) and returns the textual response from `avfilter_graph_send_command`.
+	pub fn command(&mut self, target: &str, command: &str, arg: &str) -> Result<String, Error> {
+		unsafe {
+			let mut response = [0 as libc::c_char; 4096];
+
+			match avfilter_graph_send_command(self.as_mut_ptr(),
+				CString::new(target).unwrap().as_ptr(),
+				CString::new(command).unwrap().as_ptr(),
+				CString::new(arg).unwrap().as_ptr(),
+				response.as_mut_ptr(),
+				response.len() as c_int,
+				0)
+			{
+				n if n >= 0 => Ok(from_utf8_unchecked(CStr::from_ptr(response.as_ptr()).to_bytes()).to_owned()),
+				e           => Err(Error::from(e)),
+			}
+		}
+	}
+
+	/// Queues a command to be applied to the filter(s) matching `target`
+	/// (or `"all"`) once the graph reaches presentation timestamp `ts`.
+	pub fn queue_command(&mut self, target: &str, command: &str, arg: &str, ts: f64) -> Result<(), Error> {
+		unsafe {
+			match avfilter_graph_queue_command(self.as_mut_ptr(),
+				CString::new(target).unwrap().as_ptr(),
+				CString::new(command).unwrap().as_ptr(),
+				CString::new(arg).unwrap().as_ptr(),
+				0,
+				ts as c_double)
+			{
+				n if n >= 0 => Ok(()),
+				e           => Err(Error::from(e)),
+			}
+		}
+	}
+
 	pub fn input(&mut self, name: &str, pad: usize) -> Result<Parser, Error> {
 		Parser::new(self).input(name, pad)
 	}